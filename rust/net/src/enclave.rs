@@ -0,0 +1,90 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Enclave-side connection setup shared by the SVR3 client: how many
+//! enclaves a given environment talks to and how connections to them are
+//! established, plus the capability handshake run at the start of an SVR3
+//! operation (see [`crate::svr3`]).
+
+use std::collections::HashSet;
+use std::fmt;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::infra::AsyncDuplexStream;
+use crate::svr3::Operation;
+
+/// An error opening or using a connection to an enclave environment.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying transport failed before a response was received.
+    Transport(std::io::Error),
+    /// The enclave's response could not be parsed as a valid protocol
+    /// message.
+    Protocol(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "enclave transport error: {e}"),
+            Error::Protocol(message) => write!(f, "enclave protocol error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Chooses which and how many enclaves a particular SVR3 environment talks
+/// to, and how connections to them are opened over a stream of type `S`.
+pub trait PpssSetup<S> {
+    /// The open connection(s) a [`crate::svr3::Svr3Connect::connect`] call
+    /// returns, ready for a PPSS operation or a capability handshake.
+    type Connections: EnclaveConnections + Send;
+}
+
+/// The operations this client understands, in the fixed order used to
+/// encode/decode the capability bitmask exchanged in [`handshake_capabilities`](EnclaveConnections::handshake_capabilities).
+const OPERATIONS_BY_BIT: [Operation; 4] = [
+    Operation::Backup,
+    Operation::Restore,
+    Operation::Query,
+    Operation::Remove,
+];
+
+/// A connection (or set of connections, for environments backed by more
+/// than one enclave) capable of running the SVR3 capability handshake.
+#[async_trait]
+pub trait EnclaveConnections {
+    /// Performs the protocol-version/capability round trip: sends this
+    /// client's supported protocol version and reads back the version and
+    /// set of [`Operation`]s the enclave on the other end advertises.
+    async fn handshake_capabilities(&mut self) -> Result<(u32, HashSet<Operation>), Error>;
+}
+
+#[async_trait]
+impl<T: AsyncDuplexStream> EnclaveConnections for T {
+    async fn handshake_capabilities(&mut self) -> Result<(u32, HashSet<Operation>), Error> {
+        // Wire format: the client writes its own protocol version as a
+        // big-endian u32; the enclave replies with its protocol version
+        // (u32, big-endian) followed by a one-byte bitmask of the
+        // `Operation`s it supports, ordered as in `OPERATIONS_BY_BIT`.
+        self.write_u32(crate::svr3::PROTOCOL_VERSION)
+            .await
+            .map_err(Error::Transport)?;
+        let protocol_version = self.read_u32().await.map_err(Error::Transport)?;
+        let supported_mask = self.read_u8().await.map_err(Error::Transport)?;
+
+        let supported = OPERATIONS_BY_BIT
+            .into_iter()
+            .enumerate()
+            .filter(|(bit, _)| supported_mask & (1 << bit) != 0)
+            .map(|(_, operation)| operation)
+            .collect();
+
+        Ok((protocol_version, supported))
+    }
+}
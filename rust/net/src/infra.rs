@@ -0,0 +1,17 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Transport-level abstractions shared by the networking clients in this
+//! crate.
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A duplex (bidirectional) async byte stream, usable by either end of a
+/// request/response protocol. Blanket-implemented for anything that is
+/// already readable, writable, `Unpin`, and `Send`, so callers never need
+/// to implement it directly.
+pub trait AsyncDuplexStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncDuplexStream for T {}
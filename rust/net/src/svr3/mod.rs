@@ -0,0 +1,98 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Client-side SVR3 (Secure Value Recovery 3) operations: backing up,
+//! restoring, rotating, migrating, and redundantly storing a secret across
+//! one or more attested enclave environments.
+
+mod ppss_ops;
+pub(crate) mod traits;
+
+pub use traits::*;
+pub(crate) use traits::PROTOCOL_VERSION;
+
+use std::fmt;
+
+use crate::enclave;
+
+/// Errors that can occur while performing an SVR3 operation.
+#[derive(Debug)]
+pub enum Error {
+    /// The connection to the enclave could not be established or was lost.
+    Connect(enclave::Error),
+    /// The enclave's advertised protocol version is older than what this
+    /// client requires.
+    UnsupportedVersion { required: u32, found: u32 },
+    /// The enclave's protocol version is recent enough, but it does not
+    /// advertise support for the requested operation.
+    UnsupportedOperation { operation: Operation },
+    /// A [`RedundantBackup::redundant_backup`] call was given a threshold
+    /// that isn't between 1 and the number of participating environments.
+    InvalidThreshold {
+        threshold: usize,
+        environments: usize,
+    },
+    /// A [`RedundantShare`] named an environment that wasn't among the ones
+    /// provided to the restoring call.
+    UnknownEnvironment { environment: EnvironmentId },
+    /// A [`Migrate::migrate`] verification restore from the destination
+    /// did not return the same secret that was just backed up there.
+    MigrationVerificationMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Connect(e) => write!(f, "enclave connection error: {e}"),
+            Error::UnsupportedVersion { required, found } => write!(
+                f,
+                "enclave protocol version {found} is older than the required {required}"
+            ),
+            Error::UnsupportedOperation { operation } => {
+                write!(f, "enclave does not support {operation:?}")
+            }
+            Error::InvalidThreshold {
+                threshold,
+                environments,
+            } => write!(
+                f,
+                "threshold {threshold} is not between 1 and {environments} environments"
+            ),
+            Error::UnknownEnvironment { environment } => {
+                write!(f, "unknown environment {environment:?}")
+            }
+            Error::MigrationVerificationMismatch => {
+                write!(f, "destination restore did not match the migrated secret")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<enclave::Error> for Error {
+    fn from(error: enclave::Error) -> Self {
+        Error::Connect(error)
+    }
+}
+
+/// An opaque, serializable handle to a secret backed up via [`Backup`].
+/// Clients persist this value (never the secret itself) and present it
+/// back to [`Restore`], [`Rotate`], or [`Migrate`] to recover the secret
+/// later.
+#[derive(Debug, Clone)]
+pub struct OpaqueMaskedShareSet {
+    bytes: Vec<u8>,
+}
+
+impl OpaqueMaskedShareSet {
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
@@ -0,0 +1,11 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Additions to the enclave-side PPSS dispatch used by [`super::traits`].
+//!
+//! The protocol-version/capability handshake lives on
+//! [`crate::enclave::EnclaveConnections`] rather than here: it runs over the
+//! same connection a `backup`/`restore`/`query`/`remove` call is about to
+//! use, not a separate one opened just for the handshake.
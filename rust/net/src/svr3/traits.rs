@@ -7,19 +7,108 @@
 //! therefore the module exists as a sort of a "prelude" to make importing them
 //! all in bulk easier.
 
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use futures_util::future::join_all;
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt as _;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use rand_core::CryptoRngCore;
+use tracing::Instrument as _;
+use zeroize::Zeroize;
 
 use libsignal_svr3::EvaluationResult;
 
 use crate::enclave;
-use crate::enclave::PpssSetup;
+use crate::enclave::{EnclaveConnections, PpssSetup};
 use crate::infra::AsyncDuplexStream;
 
 use super::{ppss_ops, Error, OpaqueMaskedShareSet};
 
+/// The minimum enclave protocol version this client is able to speak.
+pub(crate) const PROTOCOL_VERSION: u32 = 1;
+
+/// A single PPSS operation that an enclave may or may not advertise support for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Backup,
+    Restore,
+    Query,
+    Remove,
+}
+
+/// The protocol version and set of [`Operation`]s an enclave advertised
+/// support for during the most recent handshake on a connection.
+#[derive(Debug, Clone)]
+pub struct EnclaveCapabilities {
+    pub protocol_version: u32,
+    supported: HashSet<Operation>,
+}
+
+impl EnclaveCapabilities {
+    pub fn new(protocol_version: u32, supported: HashSet<Operation>) -> Self {
+        Self {
+            protocol_version,
+            supported,
+        }
+    }
+
+    pub fn supports(&self, operation: Operation) -> bool {
+        self.supported.contains(&operation)
+    }
+}
+
+fn ensure_supported(capabilities: &EnclaveCapabilities, operation: Operation) -> Result<(), Error> {
+    if capabilities.protocol_version < PROTOCOL_VERSION {
+        return Err(Error::UnsupportedVersion {
+            required: PROTOCOL_VERSION,
+            found: capabilities.protocol_version,
+        });
+    }
+    if !capabilities.supports(operation) {
+        return Err(Error::UnsupportedOperation { operation });
+    }
+    Ok(())
+}
+
+/// Fetches the enclave's [`EnclaveCapabilities`] over `connections`, an
+/// already-open connection, reusing `connect`'s cache instead of handshaking
+/// again if a previous call already populated it. Takes `connections` by
+/// reference rather than opening its own, so the very first call on a fresh
+/// connection still only pays for one round trip, not a handshake plus a
+/// second connect for the operation that triggered it.
+async fn capabilities_of<T>(
+    connect: &T,
+    connections: &mut <T::Env as PpssSetup<T::Stream>>::Connections,
+) -> Result<EnclaveCapabilities, Error>
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    if let Some(capabilities) = connect.cached_capabilities().await {
+        return Ok(capabilities);
+    }
+    let (protocol_version, supported) = connections.handshake_capabilities().await?;
+    let capabilities = EnclaveCapabilities::new(protocol_version, supported);
+    connect.cache_capabilities(&capabilities).await;
+    Ok(capabilities)
+}
+
+#[async_trait]
+pub trait Version {
+    /// Returns the protocol version and operations the enclave supports,
+    /// without dispatching a PPSS request. If capabilities were cached by an
+    /// earlier handshake (see [`Svr3Connect::cached_capabilities`]), those are
+    /// returned without contacting the enclave; otherwise this connects and
+    /// performs a lightweight handshake.
+    async fn capabilities(&self) -> Result<EnclaveCapabilities, Error>;
+}
+
 #[async_trait]
 pub trait Backup {
     async fn backup(
@@ -46,11 +135,159 @@ pub trait Query {
     async fn query(&self) -> Result<u32, Error>;
 }
 
+#[async_trait]
+pub trait Rotate {
+    /// Refreshes the tries remaining on a stored secret to `max_tries`
+    /// without ever materializing the secret to the caller.
+    ///
+    /// This is a plain restore followed by a backup of the restored value:
+    /// `restore` never mutates what is stored at the enclave, so if the
+    /// connection is lost before the follow-up `backup` commits, the
+    /// original `share_set` is untouched and still restorable with
+    /// `password`. The restored secret bytes are held only for the
+    /// duration of this call and are scrubbed before returning.
+    async fn rotate(
+        &self,
+        password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<OpaqueMaskedShareSet, Error>;
+}
+
 #[async_trait]
 pub trait Remove {
     async fn remove(&self) -> Result<(), Error>;
 }
 
+/// The result of a [`Migrate::migrate`] call, recording how far the
+/// migration progressed so an interrupted caller knows what is left to do.
+#[derive(Debug, Clone)]
+pub enum MigrationOutcome {
+    /// The secret is backed up at the destination, but the source copy has
+    /// not been removed. This is returned in two cases: the verification
+    /// restore from the destination succeeded and removing the source then
+    /// failed, or the verification restore itself failed, in which case the
+    /// destination copy is unverified and the source was not touched. The
+    /// variant does not say which of the two happened. The caller should
+    /// retain `destination_share_set`.
+    BackedUpPendingRemoval {
+        destination_share_set: OpaqueMaskedShareSet,
+    },
+    /// The secret was restored from the source, backed up to the
+    /// destination, verified, and removed from the source.
+    Completed {
+        destination_share_set: OpaqueMaskedShareSet,
+    },
+}
+
+#[async_trait]
+pub trait Migrate {
+    /// Moves a secret from `self` to `destination`, restoring it from the
+    /// source and re-backing it up at the destination with `max_tries`
+    /// before ever touching the source's copy. The secret bytes never leave
+    /// this function; only share sets and the [`MigrationOutcome`] checkpoint
+    /// are returned to the caller.
+    async fn migrate<D>(
+        &self,
+        destination: &D,
+        password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<MigrationOutcome, Error>
+    where
+        D: Svr3Connect + Sync,
+        D::Stream: AsyncDuplexStream + 'static;
+}
+
+/// Identifies one of several environments participating in a redundant
+/// backup or restore.
+pub type EnvironmentId = String;
+
+/// One [`shamir`]-split share of a secret, written to a single environment.
+#[derive(Debug, Clone)]
+pub struct RedundantShare {
+    pub environment: EnvironmentId,
+    pub protocol_version: u32,
+    share_index: u8,
+    share_set: OpaqueMaskedShareSet,
+}
+
+/// The combined result of a [`RedundantBackup::redundant_backup`] call: `n`
+/// Shamir shares of the secret, any `threshold` of which reconstruct it.
+/// Each entry records the environment it was written to and the protocol
+/// version that environment advertised, so a later restore knows where, and
+/// whether, to look.
+#[derive(Debug, Clone)]
+pub struct RedundantShareSet {
+    pub threshold: usize,
+    pub shares: Vec<RedundantShare>,
+}
+
+/// Returned from [`RedundantRestore::redundant_restore`] when fewer than
+/// `threshold` environments returned a valid share, with the per-environment
+/// failure (wrong password/tries exhausted, enclave unreachable, environment
+/// no longer known, etc.) for each environment whose restore failed.
+#[derive(Debug)]
+pub struct RedundantRestoreError {
+    pub threshold: usize,
+    pub succeeded: usize,
+    pub errors: HashMap<EnvironmentId, Error>,
+}
+
+#[async_trait]
+pub trait RedundantBackup {
+    /// Splits `secret` into `self.len()` Shamir shares, any `threshold` of
+    /// which reconstruct it, and backs up one share to each environment in
+    /// `self`, concurrently. `threshold` must be between 1 and `self.len()`
+    /// inclusive; this call only succeeds if every environment accepts its
+    /// share.
+    async fn redundant_backup(
+        &self,
+        threshold: usize,
+        password: &str,
+        secret: [u8; 32],
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<RedundantShareSet, Error>;
+}
+
+#[async_trait]
+pub trait RedundantRestore {
+    /// Restores concurrently from every environment named in `share_set`,
+    /// combining shares into the original secret as soon as
+    /// `share_set.threshold` of them return a valid share; in-flight
+    /// restores from the remaining environments are abandoned at that point.
+    /// If fewer than `threshold` environments ever respond successfully,
+    /// returns a [`RedundantRestoreError`] carrying the per-environment
+    /// errors for diagnosis.
+    async fn redundant_restore(
+        &self,
+        password: &str,
+        share_set: RedundantShareSet,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<EvaluationResult, RedundantRestoreError>;
+}
+
+/// Whether a `backup`/`restore`/`query`/`remove` call completed
+/// successfully, reported to a [`Svr3Observer`] alongside the elapsed time it
+/// took. A failure to connect or an unsupported capability is reported as
+/// [`Outcome::Failure`] even though no [`ppss_ops`] call was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+/// Observes `backup`/`restore`/`query`/`remove` calls made through a
+/// [`Svr3Connect`] implementor, so an embedder can measure latency and log
+/// completed requests without forking the trait impls in this module.
+pub trait Svr3Observer: Send + Sync {
+    fn on_start(&self, operation: Operation);
+    fn on_complete(&self, operation: Operation, outcome: Outcome, elapsed: Duration);
+}
+
 #[async_trait]
 pub trait Svr3Connect {
     // Stream is needed for the blanket implementation,
@@ -60,6 +297,71 @@ pub trait Svr3Connect {
     async fn connect(
         &self,
     ) -> Result<<Self::Env as PpssSetup<Self::Stream>>::Connections, enclave::Error>;
+
+    /// Returns the [`EnclaveCapabilities`] cached from a previous handshake
+    /// on this connection-owning instance, if any. The default never
+    /// caches, so the handshake runs on every call; implementors that want
+    /// to skip it on repeat operations should override this alongside
+    /// [`cache_capabilities`](Self::cache_capabilities) to read/write a
+    /// field of their own (e.g. a `tokio::sync::OnceCell`).
+    async fn cached_capabilities(&self) -> Option<EnclaveCapabilities> {
+        None
+    }
+
+    /// Records `capabilities` for a later `cached_capabilities` call. The
+    /// default is a no-op; see `cached_capabilities`.
+    async fn cache_capabilities(&self, _capabilities: &EnclaveCapabilities) {}
+
+    /// The [`Svr3Observer`] to notify of this connection's operations, if
+    /// any. Defaults to none; implementors wanting metrics or logging should
+    /// override this to return a configured observer.
+    fn observer(&self) -> Option<&dyn Svr3Observer> {
+        None
+    }
+
+    /// A human-readable identifier for the enclave environment this
+    /// connection targets (e.g. its MRENCLAVE or deployment name), attached
+    /// to tracing spans and useful for telling environments apart in logs.
+    fn environment_name(&self) -> &str {
+        "unknown"
+    }
+}
+
+async fn with_observer<T, F, R>(connect: &T, operation: Operation, fut: F) -> Result<R, Error>
+where
+    T: Svr3Connect + Sync,
+    F: Future<Output = Result<R, Error>> + Send,
+{
+    if let Some(observer) = connect.observer() {
+        observer.on_start(operation);
+    }
+    let start = Instant::now();
+    let span = tracing::info_span!("svr3_op", ?operation, environment = connect.environment_name());
+    let result = fut.instrument(span).await;
+    if let Some(observer) = connect.observer() {
+        let outcome = if result.is_ok() {
+            Outcome::Success
+        } else {
+            Outcome::Failure
+        };
+        observer.on_complete(operation, outcome, start.elapsed());
+    }
+    result
+}
+
+#[async_trait]
+impl<T> Version for T
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    async fn capabilities(&self) -> Result<EnclaveCapabilities, Error> {
+        if let Some(capabilities) = self.cached_capabilities().await {
+            return Ok(capabilities);
+        }
+        let mut connections = self.connect().await?;
+        capabilities_of(self, &mut connections).await
+    }
 }
 
 #[async_trait]
@@ -75,13 +377,13 @@ where
         max_tries: NonZeroU32,
         rng: &mut (impl CryptoRngCore + Send),
     ) -> Result<OpaqueMaskedShareSet, Error> {
-        ppss_ops::do_backup::<T::Stream, T::Env>(
-            self.connect().await?,
-            password,
-            secret,
-            max_tries,
-            rng,
-        )
+        with_observer(self, Operation::Backup, async {
+            let mut connections = self.connect().await?;
+            let capabilities = capabilities_of(self, &mut connections).await?;
+            ensure_supported(&capabilities, Operation::Backup)?;
+            ppss_ops::do_backup::<T::Stream, T::Env>(connections, password, secret, max_tries, rng)
+                .await
+        })
         .await
     }
 }
@@ -98,8 +400,13 @@ where
         share_set: OpaqueMaskedShareSet,
         rng: &mut (impl CryptoRngCore + Send),
     ) -> Result<EvaluationResult, Error> {
-        ppss_ops::do_restore::<T::Stream, T::Env>(self.connect().await?, password, share_set, rng)
-            .await
+        with_observer(self, Operation::Restore, async {
+            let mut connections = self.connect().await?;
+            let capabilities = capabilities_of(self, &mut connections).await?;
+            ensure_supported(&capabilities, Operation::Restore)?;
+            ppss_ops::do_restore::<T::Stream, T::Env>(connections, password, share_set, rng).await
+        })
+        .await
     }
 }
 
@@ -110,7 +417,450 @@ where
     T::Stream: AsyncDuplexStream + 'static,
 {
     async fn remove(&self) -> Result<(), Error> {
-        ppss_ops::do_remove::<T::Stream, T::Env>(self.connect().await?).await
+        with_observer(self, Operation::Remove, async {
+            let mut connections = self.connect().await?;
+            let capabilities = capabilities_of(self, &mut connections).await?;
+            ensure_supported(&capabilities, Operation::Remove)?;
+            ppss_ops::do_remove::<T::Stream, T::Env>(connections).await
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl<T> Rotate for T
+where
+    T: Backup + Restore + Sync,
+{
+    async fn rotate(
+        &self,
+        password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<OpaqueMaskedShareSet, Error> {
+        let mut evaluation = self.restore(password, share_set, rng).await?;
+        let result = self.backup(password, evaluation.value, max_tries, rng).await;
+        // Scrub the restored secret regardless of whether the backup
+        // succeeded; it must never be observable past this call. `Zeroize`
+        // is used rather than a plain assignment because a dead store the
+        // optimizer is free to elide does not actually guarantee a wipe.
+        evaluation.value.zeroize();
+        result
+    }
+}
+
+#[async_trait]
+impl<T> Migrate for T
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    async fn migrate<D>(
+        &self,
+        destination: &D,
+        password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<MigrationOutcome, Error>
+    where
+        D: Svr3Connect + Sync,
+        D::Stream: AsyncDuplexStream + 'static,
+    {
+        let mut evaluation = self.restore(password, share_set, rng).await?;
+        let backup_result = destination
+            .backup(password, evaluation.value, max_tries, rng)
+            .await;
+        let destination_share_set = match backup_result {
+            Ok(destination_share_set) => destination_share_set,
+            Err(error) => {
+                evaluation.value.zeroize();
+                return Err(error);
+            }
+        };
+
+        // Confirm the destination can actually serve the secret back, and
+        // that what comes back matches what was just backed up, before
+        // touching the source. `Zeroize` (rather than a plain assignment,
+        // which is a dead store the optimizer may elide) scrubs both
+        // copies once the comparison is done, whichever way it goes.
+        let verification = destination
+            .restore(password, destination_share_set.clone(), rng)
+            .await;
+        let verification_failed = match verification {
+            Ok(mut verified) => {
+                let matches = verified.value == evaluation.value;
+                verified.value.zeroize();
+                evaluation.value.zeroize();
+                if !matches {
+                    return Err(Error::MigrationVerificationMismatch);
+                }
+                false
+            }
+            Err(_) => {
+                evaluation.value.zeroize();
+                true
+            }
+        };
+
+        if verification_failed {
+            // The destination holds a committed backup even though we
+            // couldn't confirm it reads back correctly; surface the
+            // checkpoint so the caller can retry verification (or removal)
+            // instead of losing track of it.
+            return Ok(MigrationOutcome::BackedUpPendingRemoval {
+                destination_share_set,
+            });
+        }
+
+        // The secret is now durably and verifiably available at the
+        // destination, so it is safe to remove the source copy. If this
+        // fails or the process dies here, the migration has still succeeded
+        // from the caller's perspective; retry `source.remove()` to finish.
+        if self.remove().await.is_err() {
+            return Ok(MigrationOutcome::BackedUpPendingRemoval {
+                destination_share_set,
+            });
+        }
+
+        Ok(MigrationOutcome::Completed {
+            destination_share_set,
+        })
+    }
+}
+
+/// Rejects a `threshold` that is not between 1 and `environments` inclusive,
+/// since [`shamir::split`] cannot produce a sensible sharing otherwise.
+fn validate_threshold(threshold: usize, environments: usize) -> Result<(), Error> {
+    if threshold == 0 || threshold > environments {
+        return Err(Error::InvalidThreshold {
+            threshold,
+            environments,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod redundant_tests {
+    use super::*;
+
+    #[test]
+    fn threshold_must_be_between_one_and_environment_count() {
+        assert!(validate_threshold(0, 5).is_err());
+        assert!(validate_threshold(6, 5).is_err());
+        assert!(validate_threshold(1, 5).is_ok());
+        assert!(validate_threshold(5, 5).is_ok());
+        assert!(validate_threshold(3, 5).is_ok());
+    }
+}
+
+#[async_trait]
+impl<T> RedundantBackup for [(EnvironmentId, T)]
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    async fn redundant_backup(
+        &self,
+        threshold: usize,
+        password: &str,
+        secret: [u8; 32],
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<RedundantShareSet, Error> {
+        let n = self.len();
+        validate_threshold(threshold, n)?;
+
+        let split = shamir::split(&secret, threshold as u8, n as u8, rng);
+
+        let mut seeds = Vec::with_capacity(n);
+        for _ in self {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            seeds.push(seed);
+        }
+
+        let shares = join_all(self.iter().zip(split).zip(seeds).map(
+            |(((id, env), (share_index, share)), seed)| async move {
+                let mut rng = StdRng::from_seed(seed);
+                let protocol_version = env.capabilities().await?.protocol_version;
+                let share_set = env.backup(password, share, max_tries, &mut rng).await?;
+                Ok::<_, Error>(RedundantShare {
+                    environment: id.clone(),
+                    protocol_version,
+                    share_index,
+                    share_set,
+                })
+            },
+        ))
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+
+        Ok(RedundantShareSet { threshold, shares })
+    }
+}
+
+#[async_trait]
+impl<T> RedundantRestore for [(EnvironmentId, T)]
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    async fn redundant_restore(
+        &self,
+        password: &str,
+        share_set: RedundantShareSet,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<EvaluationResult, RedundantRestoreError> {
+        let threshold = share_set.threshold;
+        let environments: HashMap<&EnvironmentId, &T> =
+            self.iter().map(|(id, env)| (id, env)).collect();
+
+        let mut seeds = Vec::with_capacity(share_set.shares.len());
+        for _ in &share_set.shares {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            seeds.push(seed);
+        }
+
+        let mut in_flight = share_set
+            .shares
+            .into_iter()
+            .zip(seeds)
+            .map(|(share, seed)| restore_one_share(&environments, password, share, seed))
+            .collect::<FuturesUnordered<_>>();
+
+        let mut gathered = Vec::new();
+        let mut errors = HashMap::new();
+        while let Some((id, outcome)) = in_flight.next().await {
+            match outcome {
+                Ok(share) => {
+                    gathered.push(share);
+                    if gathered.len() >= threshold {
+                        // We have a quorum; abandon the rest in flight.
+                        break;
+                    }
+                }
+                Err(error) => {
+                    errors.insert(id, error);
+                }
+            }
+        }
+
+        if gathered.len() >= threshold {
+            // Report the minimum tries remaining across the gathered
+            // shares, not an arbitrary one of them: the secret becomes
+            // unrecoverable as soon as any single contributing environment
+            // runs out, so the minimum is the honest worst case.
+            let tries_remaining = gathered
+                .iter()
+                .map(|&(_, _, tries)| tries)
+                .min()
+                .expect("gathered.len() >= threshold >= 1");
+            let shares: Vec<(u8, [u8; 32])> =
+                gathered.into_iter().map(|(index, value, _)| (index, value)).collect();
+            // `EvaluationResult`'s fields are `pub` and the struct is
+            // exhaustive (libsignal_svr3 has no private fields or #[non_exhaustive]
+            // marker on it), so constructing it by literal here is the
+            // intended way to assemble a combined result; there is no
+            // separate constructor to go through.
+            Ok(EvaluationResult {
+                value: shamir::combine(&shares),
+                tries_remaining,
+            })
+        } else {
+            Err(RedundantRestoreError {
+                threshold,
+                succeeded: gathered.len(),
+                errors,
+            })
+        }
+    }
+}
+
+/// Restores a single [`RedundantShare`] and returns the share's environment
+/// id paired with either its Shamir share index, the recovered share bytes,
+/// and the environment's remaining tries, or why the restore failed.
+async fn restore_one_share<T>(
+    environments: &HashMap<&EnvironmentId, &T>,
+    password: &str,
+    share: RedundantShare,
+    seed: [u8; 32],
+) -> (EnvironmentId, Result<(u8, [u8; 32], u32), Error>)
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    let Some(env) = environments.get(&share.environment) else {
+        return (
+            share.environment,
+            Err(Error::UnknownEnvironment {
+                environment: share.environment.clone(),
+            }),
+        );
+    };
+    let outcome = restore_one_share_from(*env, password, &share, seed).await;
+    (share.environment, outcome)
+}
+
+async fn restore_one_share_from<T>(
+    env: &T,
+    password: &str,
+    share: &RedundantShare,
+    seed: [u8; 32],
+) -> Result<(u8, [u8; 32], u32), Error>
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    // The environment named in the share must still speak at least the
+    // protocol version it did when the share was written; an older enclave
+    // is not guaranteed to understand the stored format.
+    let current_version = env.capabilities().await?.protocol_version;
+    if current_version < share.protocol_version {
+        return Err(Error::UnsupportedVersion {
+            required: share.protocol_version,
+            found: current_version,
+        });
+    }
+
+    let mut rng = StdRng::from_seed(seed);
+    env.restore(password, share.share_set.clone(), &mut rng)
+        .await
+        .map(|evaluation| (share.share_index, evaluation.value, evaluation.tries_remaining))
+}
+
+/// Classic (k, n) Shamir secret sharing over GF(2^8), applied byte-wise to a
+/// 32-byte secret: any `k` of the `n` shares reconstruct it, while `k - 1`
+/// shares reveal nothing about it.
+mod shamir {
+    use rand_core::CryptoRngCore;
+
+    fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+        let mut product = 0u8;
+        for _ in 0..8 {
+            if b & 1 != 0 {
+                product ^= a;
+            }
+            let high_bit = a & 0x80;
+            a <<= 1;
+            if high_bit != 0 {
+                a ^= 0x1b;
+            }
+            b >>= 1;
+        }
+        product
+    }
+
+    fn gf_pow(base: u8, mut exponent: u8) -> u8 {
+        let mut result = 1u8;
+        let mut base_pow = base;
+        while exponent > 0 {
+            if exponent & 1 != 0 {
+                result = gf_mul(result, base_pow);
+            }
+            base_pow = gf_mul(base_pow, base_pow);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// The multiplicative inverse in GF(2^8), via `a^254 == a^-1` (since
+    /// `a^255 == 1` for every nonzero `a`).
+    fn gf_inv(a: u8) -> u8 {
+        gf_pow(a, 254)
+    }
+
+    /// Splits `secret` into `n` shares such that any `threshold` of them
+    /// reconstruct it via [`combine`]. `x` coordinates are `1..=n`; `0` is
+    /// reserved for the secret itself.
+    pub(super) fn split(
+        secret: &[u8; 32],
+        threshold: u8,
+        n: u8,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Vec<(u8, [u8; 32])> {
+        assert!((1..=n).contains(&threshold));
+
+        let mut coefficients = Vec::with_capacity(threshold as usize - 1);
+        for _ in 0..threshold - 1 {
+            let mut coefficient = [0u8; 32];
+            rng.fill_bytes(&mut coefficient);
+            coefficients.push(coefficient);
+        }
+
+        (1..=n)
+            .map(|x| {
+                let mut share = *secret;
+                let mut x_power = x;
+                for coefficient in &coefficients {
+                    for byte in 0..32 {
+                        share[byte] ^= gf_mul(coefficient[byte], x_power);
+                    }
+                    x_power = gf_mul(x_power, x);
+                }
+                (x, share)
+            })
+            .collect()
+    }
+
+    /// Reconstructs the secret from `shares` via Lagrange interpolation at
+    /// `x = 0`. If fewer than the original `threshold` shares are given, the
+    /// result is not the original secret.
+    pub(super) fn combine(shares: &[(u8, [u8; 32])]) -> [u8; 32] {
+        let mut secret = [0u8; 32];
+        for (i, &(x_i, ref share_i)) in shares.iter().enumerate() {
+            let mut numerator = 1u8;
+            let mut denominator = 1u8;
+            for (j, &(x_j, _)) in shares.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                numerator = gf_mul(numerator, x_j);
+                denominator = gf_mul(denominator, x_i ^ x_j);
+            }
+            let basis = gf_mul(numerator, gf_inv(denominator));
+            for byte in 0..32 {
+                secret[byte] ^= gf_mul(share_i[byte], basis);
+            }
+        }
+        secret
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        use super::*;
+
+        const SECRET: [u8; 32] = *b"0123456789abcdef0123456789abcdef";
+
+        #[test]
+        fn any_threshold_sized_subset_reconstructs_the_secret() {
+            let shares = split(&SECRET, 3, 5, &mut StdRng::from_seed([7u8; 32]));
+            for subset in [[0, 1, 2], [0, 2, 4], [1, 3, 4], [2, 3, 4]] {
+                let chosen: Vec<_> = subset.iter().map(|&i| shares[i]).collect();
+                assert_eq!(combine(&chosen), SECRET);
+            }
+        }
+
+        #[test]
+        fn fewer_than_threshold_shares_do_not_reconstruct_the_secret() {
+            let shares = split(&SECRET, 3, 5, &mut StdRng::from_seed([7u8; 32]));
+            let chosen = [shares[0], shares[1]];
+            assert_ne!(combine(&chosen), SECRET);
+        }
+
+        #[test]
+        fn every_share_is_required_to_have_been_produced() {
+            let shares = split(&SECRET, 3, 5, &mut StdRng::from_seed([1u8; 32]));
+            assert_eq!(shares.len(), 5);
+            assert_eq!(shares.iter().map(|&(x, _)| x).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        }
     }
 }
 
@@ -121,6 +871,12 @@ where
     T::Stream: AsyncDuplexStream + 'static,
 {
     async fn query(&self) -> Result<u32, Error> {
-        ppss_ops::do_query::<T::Stream, T::Env>(self.connect().await?).await
+        with_observer(self, Operation::Query, async {
+            let mut connections = self.connect().await?;
+            let capabilities = capabilities_of(self, &mut connections).await?;
+            ensure_supported(&capabilities, Operation::Query)?;
+            ppss_ops::do_query::<T::Stream, T::Env>(connections).await
+        })
+        .await
     }
 }